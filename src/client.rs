@@ -1,5 +1,6 @@
 use std::{fmt, error::Error};
 use serde::ser::{Serialize, Serializer, SerializeStruct};
+use crate::decimal::Decimal;
 
 type Result<T> = std::result::Result<T, ClientError>;
 
@@ -25,21 +26,21 @@ impl fmt::Display for ClientError {
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Client {
     id: u16,
-    available: f64,
-    held: f64,
+    available: Decimal,
+    held: Decimal,
     locked: bool
 }
 
 impl Serialize for Client {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-    where 
+    where
         S: Serializer,
     {
         let mut state = serializer.serialize_struct("Client", 5)?;
         state.serialize_field("client", &self.id)?;
-        state.serialize_field("available", &format!("{:.4}", self.available))?;
-        state.serialize_field("held", &format!("{:.4}", self.held))?;
-        state.serialize_field("total", &format!("{:.4}", self.total()))?;
+        state.serialize_field("available", &self.available.to_string())?;
+        state.serialize_field("held", &self.held.to_string())?;
+        state.serialize_field("total", &self.total().to_string())?;
         state.serialize_field("locked", &self.locked)?;
         state.end()
     }
@@ -49,7 +50,7 @@ impl Serialize for Client {
 
 impl Client {
     #[allow(dead_code)]
-    pub fn from(id: u16, available: f64, held: f64, locked: bool) -> Self{
+    pub fn from(id: u16, available: Decimal, held: Decimal, locked: bool) -> Self{
         Client{id, available, held, locked}
     }
 
@@ -58,33 +59,61 @@ impl Client {
     }
 
     //Use negative amount for withdraw
-    pub fn deposit(&mut self, amount: f64) -> Result<()> {
-        if amount.is_sign_negative() && amount.abs() > self.available { return Err(ClientError::InsufficientFunds) }; 
+    pub fn deposit(&mut self, amount: Decimal) -> Result<()> {
+        if amount.is_negative() && amount.abs() > self.available { return Err(ClientError::InsufficientFunds) };
         self.available = self.available + amount;
         Ok(())
     }
-        
+
     //Use negative amount for release
-    pub fn hold(&mut self, amount: f64) -> Result<()>{
-        if amount.is_sign_negative() {
-            if amount.abs() > self.held { return Err(ClientError::InsufficientFunds); }        
+    pub fn hold(&mut self, amount: Decimal) -> Result<()>{
+        if amount.is_negative() {
+            if amount.abs() > self.held { return Err(ClientError::InsufficientFunds); }
         } else if amount > self.available {
-            return Err(ClientError::InsufficientFunds.into());
+            return Err(ClientError::InsufficientFunds);
         }
         self.held = self.held + amount;
         self.available = self.available - amount;
         Ok(())
     }
 
-    pub fn chargeback(&mut self, amount: f64) -> Result<()> {
-        if amount.is_sign_negative() {
-            return Err(ClientError::IncorrectSign);       
+    pub fn chargeback(&mut self, amount: Decimal) -> Result<()> {
+        if amount.is_negative() {
+            return Err(ClientError::IncorrectSign);
         }
         if amount > self.held {
             return Err(ClientError::InsufficientFunds)
         }
-        if amount.abs() > self.held { return Err(ClientError::InsufficientFunds); } 
-        self.held = self.held - amount;       
+        if amount.abs() > self.held { return Err(ClientError::InsufficientFunds); }
+        self.held = self.held - amount;
+        self.locked = true;
+        Ok(())
+    }
+
+    //A disputed withdrawal already left `available` when it was processed,
+    //so holding it must not draw from `available` a second time; it only
+    //grows `held` to record the provisional claim. Use a negative amount to
+    //release the hold again (e.g. on resolve), mirroring `hold`'s convention.
+    pub fn hold_withdrawal(&mut self, amount: Decimal) -> Result<()> {
+        if amount.is_negative() && amount.abs() > self.held {
+            return Err(ClientError::InsufficientFunds);
+        }
+        self.held = self.held + amount;
+        Ok(())
+    }
+
+    //Charging back a disputed withdrawal returns the withdrawn funds to
+    //`available` instead of destroying them, since the client should end up
+    //as if the fraudulent withdrawal never happened.
+    pub fn chargeback_withdrawal(&mut self, amount: Decimal) -> Result<()> {
+        if amount.is_negative() {
+            return Err(ClientError::IncorrectSign);
+        }
+        if amount > self.held {
+            return Err(ClientError::InsufficientFunds);
+        }
+        self.held = self.held - amount;
+        self.available = self.available + amount;
         self.locked = true;
         Ok(())
     }
@@ -94,22 +123,21 @@ impl Client {
         self.locked = locked.to_owned();
     }
 
-    #[allow(dead_code)]
     pub fn locked(&self) -> bool {
         self.locked
     }
 
-    pub fn total(&self) -> f64 {
+    pub fn total(&self) -> Decimal {
         self.available + self.held
     }
 
     #[allow(dead_code)]
-    pub fn available(&self) -> f64 {
+    pub fn available(&self) -> Decimal {
         self.available
     }
 
     #[allow(dead_code)]
-    pub fn held(&self) -> f64 {
+    pub fn held(&self) -> Decimal {
         self.held
     }
 
@@ -124,16 +152,12 @@ mod tests {
     use super::*;
     use std::io;
 
-    const AVAILABLE: f64 = 100.1221;
-    const HELD: f64 = 2345.5443;
-    const LOCKED: bool = false;
-
     fn get_test_client() -> Client {
         Client {
             id: 1,
-            available: AVAILABLE,
-            held: HELD,
-            locked: LOCKED,
+            available: "100.1221".parse().unwrap(),
+            held: "2345.5443".parse().unwrap(),
+            locked: false,
         }
     }
 
@@ -147,49 +171,55 @@ mod tests {
     #[test]
     fn test_deposit() {
         let mut client = get_test_client();
-        assert_eq!(client.total(), AVAILABLE + HELD);
-        let deposit = 1345.678;
+        let available: Decimal = "100.1221".parse().unwrap();
+        let held: Decimal = "2345.5443".parse().unwrap();
+        assert_eq!(client.total(), available + held);
+        let deposit: Decimal = "1345.678".parse().unwrap();
         client.deposit(deposit).unwrap();
-        assert_eq!(client.total(), AVAILABLE + HELD + deposit);
+        assert_eq!(client.total(), available + held + deposit);
         let this_available = client.available();
         client.deposit(-this_available).unwrap();
-        assert_eq!(client.available(), 0.0);
-        assert_eq!(client.deposit(-1.0), Err(ClientError::InsufficientFunds));
+        assert_eq!(client.available(), Decimal::ZERO);
+        assert_eq!(client.deposit(-"1.0".parse::<Decimal>().unwrap()), Err(ClientError::InsufficientFunds));
     }
 
     #[test]
     fn test_hold() {
         let mut client = get_test_client();
-        assert_eq!(client.total(), AVAILABLE + HELD);
-        let hold = 50.21;
+        let available: Decimal = "100.1221".parse().unwrap();
+        let held: Decimal = "2345.5443".parse().unwrap();
+        assert_eq!(client.total(), available + held);
+        let hold: Decimal = "50.21".parse().unwrap();
         client.hold(hold).unwrap();
-        assert_eq!(client.total(), AVAILABLE + HELD);
+        assert_eq!(client.total(), available + held);
         let this_available = client.available();
         client.hold(this_available).unwrap();
-        assert_eq!(client.available(), 0.0);
-        assert_eq!(client.total(), AVAILABLE + HELD);
-        assert_eq!(client.hold(1.0), Err(ClientError::InsufficientFunds));
+        assert_eq!(client.available(), Decimal::ZERO);
+        assert_eq!(client.total(), available + held);
+        assert_eq!(client.hold("1.0".parse().unwrap()), Err(ClientError::InsufficientFunds));
         client.hold(-client.total()).unwrap();
-        assert_eq!(client.available(), AVAILABLE + HELD);
-        assert_eq!(client.held(), 0.0);
-        assert_eq!(client.hold(-1.0), Err(ClientError::InsufficientFunds));
+        assert_eq!(client.available(), available + held);
+        assert_eq!(client.held(), Decimal::ZERO);
+        assert_eq!(client.hold(-"1.0".parse::<Decimal>().unwrap()), Err(ClientError::InsufficientFunds));
     }
 
     #[test]
     fn test_lock() {
         let mut client = get_test_client();
-        assert_eq!(client.locked(), LOCKED);
+        assert!(!client.locked());
         client.set_locked(true);
-        assert_eq!(client.locked(), true);
+        assert!(client.locked());
     }
 
     #[test]
     fn test_amounts() {
         let client = get_test_client();
-        assert_eq!(client.locked(), LOCKED);
-        assert_eq!(client.available(), AVAILABLE);
-        assert_eq!(client.held(), HELD);
-        assert_eq!(client.total(), HELD + AVAILABLE);
+        let available: Decimal = "100.1221".parse().unwrap();
+        let held: Decimal = "2345.5443".parse().unwrap();
+        assert!(!client.locked());
+        assert_eq!(client.available(), available);
+        assert_eq!(client.held(), held);
+        assert_eq!(client.total(), held + available);
     }
 
-}
\ No newline at end of file
+}
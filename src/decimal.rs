@@ -0,0 +1,174 @@
+use std::{error::Error, fmt, ops::{Add, Neg, Sub}, str::FromStr};
+use serde::de::Error as DeError;
+use serde::ser::{Serialize, Serializer};
+use serde::de::{Deserialize, Deserializer};
+
+//Scale used to represent money as an integer number of ten-thousandths,
+//so arithmetic never drifts the way float addition does.
+const SCALE: i64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecimalError{
+    TooManyFractionalDigits(String),
+    Invalid(String),
+}
+
+impl Error for DecimalError {}
+
+impl fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecimalError::TooManyFractionalDigits(s) => write!(f, "Too many fractional digits in {}", s),
+            DecimalError::Invalid(s) => write!(f, "Invalid decimal {}", s),
+        }
+    }
+}
+
+//Fixed-point money amount with exactly 4 fractional digits, stored as an
+//integer number of ten-thousandths so `available + held == total` exactly.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal(i64);
+
+impl Decimal {
+    #[allow(dead_code)]
+    pub const ZERO: Decimal = Decimal(0);
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn abs(&self) -> Decimal {
+        Decimal(self.0.abs())
+    }
+
+    //Raw ten-thousandths, for backends (e.g. a binary on-disk format) that
+    //need a fixed-width representation instead of going through `Display`.
+    #[allow(dead_code)]
+    pub(crate) fn to_raw(self) -> i64 {
+        self.0
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn from_raw(raw: i64) -> Decimal {
+        Decimal(raw)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = DecimalError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let (neg, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+
+        let mut parts = unsigned.splitn(2, '.');
+        let int_part = parts.next().unwrap_or("");
+        let frac_part = parts.next().unwrap_or("");
+
+        if frac_part.len() > 4 {
+            return Err(DecimalError::TooManyFractionalDigits(trimmed.to_string()));
+        }
+
+        let int_val: i64 = if int_part.is_empty() { 0 } else {
+            int_part.parse().map_err(|_| DecimalError::Invalid(trimmed.to_string()))?
+        };
+        let frac_val: i64 = if frac_part.is_empty() { 0 } else {
+            frac_part.parse().map_err(|_| DecimalError::Invalid(trimmed.to_string()))?
+        };
+        let frac_val = frac_val * 10i64.pow((4 - frac_part.len()) as u32);
+
+        let magnitude = int_val * SCALE + frac_val;
+        Ok(Decimal(if neg { -magnitude } else { magnitude }))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let magnitude = self.0.abs();
+        write!(f, "{}{}.{:04}", if self.0 < 0 { "-" } else { "" }, magnitude / SCALE, magnitude % SCALE)
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+    fn add(self, other: Decimal) -> Decimal {
+        Decimal(self.0 + other.0)
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+    fn sub(self, other: Decimal) -> Decimal {
+        Decimal(self.0 - other.0)
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+    fn neg(self) -> Decimal {
+        Decimal(-self.0)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(DeError::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("10".parse::<Decimal>().unwrap().to_string(), "10.0000");
+        assert_eq!("10.0".parse::<Decimal>().unwrap().to_string(), "10.0000");
+        assert_eq!("2.742".parse::<Decimal>().unwrap().to_string(), "2.7420");
+        assert_eq!("-2.742".parse::<Decimal>().unwrap().to_string(), "-2.7420");
+        assert_eq!("0.0001".parse::<Decimal>().unwrap().to_string(), "0.0001");
+    }
+
+    #[test]
+    fn test_rejects_too_many_fractional_digits() {
+        assert_eq!(
+            "1.23456".parse::<Decimal>(),
+            Err(DecimalError::TooManyFractionalDigits("1.23456".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        let mut total = Decimal::ZERO;
+        for _ in 0..10_000 {
+            total = total + "2.742".parse::<Decimal>().unwrap();
+        }
+        assert_eq!(total.to_string(), "27420.0000");
+    }
+
+    #[test]
+    fn test_ordering_and_sign() {
+        let a = "5.0".parse::<Decimal>().unwrap();
+        let b = "-5.0".parse::<Decimal>().unwrap();
+        assert!(b.is_negative());
+        assert!(!a.is_negative());
+        assert_eq!(b.abs(), a);
+        assert!(b < a);
+    }
+}
@@ -1,16 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use crate::client::Client;
+use crate::decimal::Decimal;
+use crate::store::{InMemoryTransactionStore, TransactionStore};
 use crate::transaction::{Transaction, TransactionType::*, TransactionType};
 use std::{fmt, io, error, error::Error, default::Default};
 
 
 type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TxState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum LedgerError{
     MissingClient(u16),
     MissingTransaction(u32),
     MissingTransactionAmount(u32),
+    AlreadyDisputed(u32),
+    NotDisputed(u32),
+    FrozenAccount(u16),
 }
 
 impl Error for LedgerError {}
@@ -23,40 +36,61 @@ impl fmt::Display for LedgerError {
             LedgerError::MissingClient(id) => write!(f, "Missing client for id {}", id),
             LedgerError::MissingTransaction(id) => write!(f, "Missing transaction for id {}", id),
             LedgerError::MissingTransactionAmount(id) => write!(f, "Missing transaction amaount for id {}", id),
+            LedgerError::AlreadyDisputed(id) => write!(f, "Transaction {} is already disputed", id),
+            LedgerError::NotDisputed(id) => write!(f, "Transaction {} is not under dispute", id),
+            LedgerError::FrozenAccount(id) => write!(f, "Account {} is frozen", id),
         }
     }
 }
 
 #[derive(Default)]
-pub struct Ledger {
-    transaction_table: HashMap<u32,Transaction>,
+pub struct Ledger<S: TransactionStore = InMemoryTransactionStore> {
+    transaction_table: S,
+    tx_state_table: HashMap<u32, TxState>,
     client_table: HashMap<u16, Client>,
 }
 
-impl Ledger {
+impl<S: TransactionStore> Ledger<S> {
     pub fn apply_transaction(&mut self, tx: &Transaction) -> Result<()>{
         //Get or make new client
-        
+
+        if let Some(c) = self.client_table.get(tx.client()) {
+            if c.locked() {
+                return Err(Box::new(LedgerError::FrozenAccount(*tx.client())));
+            }
+        }
+
         match tx.tx_type() {
             deposit => {
+                let amount = tx.amount().ok_or_else(|| LedgerError::MissingTransactionAmount(*tx.tx()))?;
                 self.init_client(tx.client());
                 let c = self.get_client(tx.client())?;
-                c.deposit(tx.amount().unwrap())?;  
-                self.transaction_table.insert(*tx.tx(), *tx);
+                c.deposit(amount)?;
+                self.transaction_table.insert(tx);
+                self.tx_state_table.insert(*tx.tx(), TxState::Processed);
             },
             withdrawal => {
+                let amount = tx.amount().ok_or_else(|| LedgerError::MissingTransactionAmount(*tx.tx()))?;
                 let c = self.get_client(tx.client())?;
-                c.deposit(-tx.amount().unwrap())?;
-                self.transaction_table.insert(*tx.tx(), *tx);
+                c.deposit(-amount)?;
+                self.transaction_table.insert(tx);
+                self.tx_state_table.insert(*tx.tx(), TxState::Processed);
             },
             dispute => {
                 match self.transaction_table.get(tx.tx()){
                     Some(ref_tx) => {
-                        if *ref_tx.tx_type() == deposit {
+                        let ref_type = *ref_tx.tx_type();
+                        if ref_type == deposit || ref_type == withdrawal {
+                            self.require_state(tx.tx(), TxState::Processed, LedgerError::AlreadyDisputed(*tx.tx()))?;
                             match ref_tx.amount() {
                                 Some(a) => {
                                     let c = self.get_client(tx.client())?;
-                                    c.hold(a)?;
+                                    if ref_type == deposit {
+                                        c.hold(a)?;
+                                    } else {
+                                        c.hold_withdrawal(a)?;
+                                    }
+                                    self.tx_state_table.insert(*tx.tx(), TxState::Disputed);
                                 },
                                 None => return Err(Box::new(LedgerError::MissingTransactionAmount(*tx.tx())))
                             }
@@ -68,24 +102,44 @@ impl Ledger {
             },
             resolve => {
                 let (amount, tx_type) = self.get_tx_amount_type(tx.tx())?;
-                if tx_type == deposit {
+                if tx_type == deposit || tx_type == withdrawal {
+                    self.require_state(tx.tx(), TxState::Disputed, LedgerError::NotDisputed(*tx.tx()))?;
                     let c = self.get_client(tx.client())?;
-                    c.hold(-amount)?;
+                    if tx_type == deposit {
+                        c.hold(-amount)?;
+                    } else {
+                        c.hold_withdrawal(-amount)?;
+                    }
+                    self.tx_state_table.insert(*tx.tx(), TxState::Resolved);
                 }
             },
             chargeback => {
                 let (amount, tx_type) = self.get_tx_amount_type(tx.tx())?;
-                if tx_type == deposit {
+                if tx_type == deposit || tx_type == withdrawal {
+                    self.require_state(tx.tx(), TxState::Disputed, LedgerError::NotDisputed(*tx.tx()))?;
                     let c = self.get_client(tx.client())?;
-                    c.chargeback(amount)?;
+                    if tx_type == deposit {
+                        c.chargeback(amount)?;
+                    } else {
+                        c.chargeback_withdrawal(amount)?;
+                    }
+                    self.tx_state_table.insert(*tx.tx(), TxState::ChargedBack);
                 }
             },
         };
-        
+
         Ok(())
     }
 
-    fn get_tx_amount_type(&self, id: &u32) -> Result<(f64, TransactionType)> {
+    //Check that a tx is currently in `expected` state, otherwise return `err`
+    fn require_state(&self, id: &u32, expected: TxState, err: LedgerError) -> Result<()> {
+        match self.tx_state_table.get(id) {
+            Some(state) if *state == expected => Ok(()),
+            _ => Err(Box::new(err)),
+        }
+    }
+
+    fn get_tx_amount_type(&self, id: &u32) -> Result<(Decimal, TransactionType)> {
         let tx = self.get_transaction(id)?;
         match tx.amount(){
             Some(a) => Ok((a, *tx.tx_type())),
@@ -95,7 +149,7 @@ impl Ledger {
 
     //Create a default client if one does not yet exist
     fn init_client(&mut self, id: &u16){
-        if  self.client_table.contains_key(id) == false {
+        if !self.client_table.contains_key(id) {
             self.client_table.insert(*id, Client::from_id(*id));
         }
     }
@@ -107,7 +161,7 @@ impl Ledger {
         }
     }
 
-    fn get_transaction(&self, id: &u32) -> Result<&Transaction>{
+    fn get_transaction(&self, id: &u32) -> Result<Transaction>{
         match self.transaction_table.get(id){
             Some(c) => Ok(c),
             None => Err(Box::new(LedgerError::MissingTransaction(*id)))
@@ -116,7 +170,9 @@ impl Ledger {
 
     pub fn write_output(self){
         let mut wtr = csv::Writer::from_writer(io::stdout());
-        for (_id, client) in self.client_table {
+        //Sort by client id so output is deterministic across runs.
+        let sorted: BTreeMap<u16, Client> = self.client_table.into_iter().collect();
+        for (_id, client) in sorted {
             wtr.serialize(client).unwrap();
         }
         wtr.flush().unwrap();
@@ -127,24 +183,23 @@ impl Ledger {
 mod tests {
 
    use super::*;
+    use crate::store::DiskTransactionStore;
+
     #[test]
     fn test_apply_transactions() {
         let mut ledger : Ledger = Default::default();
 
         let txs = vec![
-            Transaction::from(deposit, 1, 1, Some(10.0)),
-            Transaction::from(deposit, 1, 2, Some(5.0)),
-            Transaction::from(withdrawal, 1, 3, Some(1.0)),
-            Transaction::from(dispute, 1, 2, None),
-            Transaction::from(resolve, 1, 2, None),
-            Transaction::from(withdrawal, 1, 3, Some(1.0)),
+            Transaction::from(deposit, 1, 1, Some("10.0".parse().unwrap())),
+            Transaction::from(deposit, 1, 2, Some("5.0".parse().unwrap())),
+            Transaction::from(withdrawal, 1, 3, Some("1.0".parse().unwrap())),
+            Transaction::from(dispute,1,999,None),
             Transaction::from(dispute, 1, 2, None),
             Transaction::from(chargeback, 1, 2, None),
-            Transaction::from(deposit, 2, 4, Some(10.0)),
-            Transaction::from(withdrawal, 2, 5, Some(5.0)),
-            Transaction::from(deposit, 2, 6, Some(5.0)),
+            Transaction::from(deposit, 2, 4, Some("10.0".parse().unwrap())),
+            Transaction::from(withdrawal, 2, 5, Some("5.0".parse().unwrap())),
+            Transaction::from(deposit, 2, 6, Some("5.0".parse().unwrap())),
             Transaction::from(dispute, 2, 6, None),
-            Transaction::from(dispute,1,999,None),
             ];
 
         for tx in txs {
@@ -152,8 +207,8 @@ mod tests {
         }
 
         let clients_expected = vec![
-            Client::from(1, 8.0, 0.0, true),
-            Client::from(2, 5.0, 5.0, false)
+            Client::from(1, "9.0".parse().unwrap(), "0.0".parse().unwrap(), true),
+            Client::from(2, "5.0".parse().unwrap(), "5.0".parse().unwrap(), false)
         ];
 
         assert_eq!(clients_expected.len(), ledger.client_table.len());
@@ -166,4 +221,103 @@ mod tests {
         ledger.write_output();
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dispute_state_machine() {
+        let mut ledger : Ledger = Default::default();
+
+        ledger.apply_transaction(&Transaction::from(deposit, 1, 1, Some("10.0".parse().unwrap()))).unwrap();
+
+        //Resolving before a dispute exists is illegal
+        let err = ledger.apply_transaction(&Transaction::from(resolve, 1, 1, None)).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::NotDisputed(1).to_string());
+
+        //Charging back before a dispute exists is illegal
+        let err = ledger.apply_transaction(&Transaction::from(chargeback, 1, 1, None)).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::NotDisputed(1).to_string());
+
+        ledger.apply_transaction(&Transaction::from(dispute, 1, 1, None)).unwrap();
+
+        //Disputing an already-disputed tx is illegal
+        let err = ledger.apply_transaction(&Transaction::from(dispute, 1, 1, None)).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::AlreadyDisputed(1).to_string());
+
+        ledger.apply_transaction(&Transaction::from(resolve, 1, 1, None)).unwrap();
+
+        //Disputing a resolved tx is illegal
+        let err = ledger.apply_transaction(&Transaction::from(dispute, 1, 1, None)).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::AlreadyDisputed(1).to_string());
+    }
+
+    #[test]
+    fn test_deposit_and_withdrawal_without_amount_are_errors() {
+        //The CSV reader is flexible enough to deserialize a short row (no
+        //amount column) into `Transaction{ amount: None, .. }`; deposit and
+        //withdrawal must reject that instead of panicking on an unwrap.
+        let mut ledger : Ledger = Default::default();
+
+        let err = ledger.apply_transaction(&Transaction::from(deposit, 1, 1, None)).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::MissingTransactionAmount(1).to_string());
+
+        let err = ledger.apply_transaction(&Transaction::from(withdrawal, 1, 2, None)).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::MissingTransactionAmount(2).to_string());
+    }
+
+    #[test]
+    fn test_frozen_account_rejects_further_transactions() {
+        let mut ledger : Ledger = Default::default();
+
+        ledger.apply_transaction(&Transaction::from(deposit, 1, 1, Some("10.0".parse().unwrap()))).unwrap();
+        ledger.apply_transaction(&Transaction::from(dispute, 1, 1, None)).unwrap();
+        ledger.apply_transaction(&Transaction::from(chargeback, 1, 1, None)).unwrap();
+
+        assert!(ledger.client_table.get(&1).unwrap().locked());
+
+        let err = ledger.apply_transaction(&Transaction::from(deposit, 1, 2, Some("5.0".parse().unwrap()))).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::FrozenAccount(1).to_string());
+
+        let err = ledger.apply_transaction(&Transaction::from(withdrawal, 1, 1, Some("1.0".parse().unwrap()))).unwrap_err();
+        assert_eq!(err.to_string(), LedgerError::FrozenAccount(1).to_string());
+    }
+
+    #[test]
+    fn test_withdrawal_dispute_chargeback_restores_funds() {
+        let mut ledger : Ledger = Default::default();
+
+        ledger.apply_transaction(&Transaction::from(deposit, 1, 1, Some("10.0".parse().unwrap()))).unwrap();
+        ledger.apply_transaction(&Transaction::from(withdrawal, 1, 2, Some("4.0".parse().unwrap()))).unwrap();
+
+        let client = *ledger.client_table.get(&1).unwrap();
+        assert_eq!(client.available(), "6.0".parse().unwrap());
+
+        //Disputing the withdrawal reserves the amount without touching
+        //`available` again, since it already left on withdrawal.
+        ledger.apply_transaction(&Transaction::from(dispute, 1, 2, None)).unwrap();
+        let client = *ledger.client_table.get(&1).unwrap();
+        assert_eq!(client.available(), "6.0".parse().unwrap());
+        assert_eq!(client.held(), "4.0".parse().unwrap());
+
+        //Charging back the withdrawal returns the funds to `available` and
+        //freezes the account.
+        ledger.apply_transaction(&Transaction::from(chargeback, 1, 2, None)).unwrap();
+        let client = *ledger.client_table.get(&1).unwrap();
+        assert_eq!(client.available(), "10.0".parse().unwrap());
+        assert_eq!(client.held(), Decimal::ZERO);
+        assert!(client.locked());
+    }
+
+    #[test]
+    fn test_disk_backed_transaction_store() {
+        //The ledger logic is identical regardless of which store backs it.
+        let mut ledger : Ledger<DiskTransactionStore> = Default::default();
+
+        ledger.apply_transaction(&Transaction::from(deposit, 1, 1, Some("10.0".parse().unwrap()))).unwrap();
+        ledger.apply_transaction(&Transaction::from(dispute, 1, 1, None)).unwrap();
+        ledger.apply_transaction(&Transaction::from(chargeback, 1, 1, None)).unwrap();
+
+        let client = *ledger.client_table.get(&1).unwrap();
+        assert_eq!(client.available(), Decimal::ZERO);
+        assert_eq!(client.held(), Decimal::ZERO);
+        assert!(client.locked());
+    }
+}
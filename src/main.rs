@@ -3,40 +3,86 @@ extern crate csv;
 extern crate serde_derive;
 
 mod client;
+mod decimal;
 mod ledger;
+mod store;
 mod transaction;
 
 use ledger::Ledger;
-use std::{fs::File, env};
+use store::{DiskTransactionStore, TransactionStore};
+use transaction::Transaction;
+use csv::{ReaderBuilder, Trim};
+use std::{fs::File, env, io, process};
 
 
+fn build_reader<R: io::Read>(reader: R) -> csv::Reader<R> {
+    ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(reader)
+}
+
+//Deserializes and applies every record, reporting failures to stderr
+//instead of aborting the run. In `strict` mode the first failure stops
+//the loop early and the return value tells the caller to abort; `process`
+//itself never terminates the process, so it stays testable.
+fn process<R: io::Read, S: TransactionStore>(rdr: &mut csv::Reader<R>, ledger: &mut Ledger<S>, strict: bool) -> bool {
+    for (line, record) in rdr.deserialize::<Transaction>().enumerate() {
+        let result = record
+            .map_err(|e| e.to_string())
+            .and_then(|tx| ledger.apply_transaction(&tx).map_err(|e| e.to_string()));
+
+        if let Err(reason) = result {
+            //+2: csv line numbers are 1-based and the header row isn't counted by `enumerate`
+            eprintln!("line {}: {}", line + 2, reason);
+            if strict {
+                return false;
+            }
+        }
+    }
+    true
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let f = File::open(&args[1]).unwrap();
-    let mut rdr = csv::Reader::from_reader(f);
-    
-
-    let mut ledger: Ledger = Default::default();
+    let strict = args.iter().any(|a| a == "--strict");
+    let disk_backed = args.iter().any(|a| a == "--disk-backed");
+    let path = args.iter().skip(1).find(|a| !a.starts_with("--"))
+        .expect("usage: payments_engine <input.csv> [--strict] [--disk-backed]");
+    let f = File::open(path).unwrap();
+    let mut rdr = build_reader(f);
+
+    //`--disk-backed` trades the in-memory store for `DiskTransactionStore`,
+    //which spills transaction history to a file instead of holding it all in RAM.
+    let ok = if disk_backed {
+        let mut ledger: Ledger<DiskTransactionStore> = Default::default();
+        let ok = process(&mut rdr, &mut ledger, strict);
+        ledger.write_output();
+        ok
+    } else {
+        let mut ledger: Ledger = Default::default();
+        let ok = process(&mut rdr, &mut ledger, strict);
+        ledger.write_output();
+        ok
+    };
 
-    for tx in rdr.deserialize(){
-        ledger.apply_transaction(&tx.unwrap()).unwrap();
+    if !ok {
+        process::exit(1);
     }
-        
-    ledger.write_output();
 }
 
 
 #[cfg(test)]
 mod tests {
 
-    use super::*;    
+    use super::*;
 
-    const INPUT: &str = "type,client,tx,amount\ndeposit,1,1,10.0\nwithdrawal,1,2,2.0\ndispute,1,2,\nresolve,1,2,\nwithdrawal,1,3,2.0\ndispute,1,3,\nchargeback,1,3,\n";    
+    const INPUT: &str = "type,client,tx,amount\ndeposit,1,1,10.0\nwithdrawal,1,2,2.0\ndispute,1,2,\nresolve,1,2,\nwithdrawal,1,3,2.0\ndispute,1,3,\nchargeback,1,3,\n";
 
     #[test]
     fn test_io() {
         let mut ledger : Ledger = Default::default();
-        let mut rdr = csv::Reader::from_reader(INPUT.as_bytes());        
+        let mut rdr = build_reader(INPUT.as_bytes());
         for tx in rdr.deserialize(){
             let txu = tx.unwrap();
             ledger.apply_transaction(&txu).unwrap();
@@ -44,9 +90,41 @@ mod tests {
         ledger.write_output();
     }
 
+    #[test]
+    fn test_tolerant_parsing() {
+        //Whitespace after commas and a short row (no trailing amount column)
+        //should both deserialize cleanly.
+        const PADDED_INPUT: &str = "type, client, tx, amount\ndeposit, 1, 1, 10.0\ndispute, 1, 1\n";
+        let mut ledger : Ledger = Default::default();
+        let mut rdr = build_reader(PADDED_INPUT.as_bytes());
+        for tx in rdr.deserialize(){
+            let txu = tx.unwrap();
+            ledger.apply_transaction(&txu).unwrap();
+        }
+    }
 
+    #[test]
+    fn test_recoverable_mode_continues_after_errors() {
+        //A malformed row and a business error (insufficient funds) are
+        //reported and skipped, not allowed to abort the whole run.
+        const DIRTY_INPUT: &str = "type,client,tx,amount\ndeposit,1,1,10.0\nwithdrawal,1,2,999.0\nnotarealtype,1,3,5.0\ndeposit,1,4,5.0\n";
+        let mut ledger : Ledger = Default::default();
+        let mut rdr = build_reader(DIRTY_INPUT.as_bytes());
+        assert!(process(&mut rdr, &mut ledger, false));
+        ledger.write_output();
+    }
 
-}
-
-
+    #[test]
+    fn test_strict_mode_stops_after_first_error() {
+        //The withdrawal on line 3 fails (insufficient funds); strict mode
+        //should report it and return false without reaching the deposit on line 4.
+        const DIRTY_INPUT: &str = "type,client,tx,amount\ndeposit,1,1,10.0\nwithdrawal,1,2,999.0\ndeposit,1,3,5.0\n";
+        let mut ledger : Ledger = Default::default();
+        let mut rdr = build_reader(DIRTY_INPUT.as_bytes());
+        assert!(!process(&mut rdr, &mut ledger, true));
+        //Only the header and the first two data rows (deposit, withdrawal)
+        //were read from the CSV; the deposit on line 4 was never reached.
+        assert_eq!(rdr.position().record(), 3);
+    }
 
+}
@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::decimal::Decimal;
+use crate::transaction::{Transaction, TransactionType};
+
+//Backs `Ledger`'s memory of past transactions, so disputes/resolves/
+//chargebacks can look up the deposit or withdrawal they refer to.
+pub trait TransactionStore: Default {
+    fn insert(&mut self, tx: &Transaction);
+    fn get(&self, id: &u32) -> Option<Transaction>;
+}
+
+//Keeps every transaction in memory. Fast, and fine for tests and small
+//inputs, but holds the whole history in RAM.
+#[derive(Default)]
+pub struct InMemoryTransactionStore {
+    table: HashMap<u32, Transaction>,
+}
+
+impl TransactionStore for InMemoryTransactionStore {
+    fn insert(&mut self, tx: &Transaction) {
+        self.table.insert(*tx.tx(), *tx);
+    }
+
+    fn get(&self, id: &u32) -> Option<Transaction> {
+        self.table.get(id).copied()
+    }
+}
+
+//Fixed-width binary encoding of a `Transaction`, so records can be seeked
+//to by byte offset on disk: type(1) + client(2) + tx(4) + amount tag(1) + amount(8).
+const RECORD_SIZE: usize = 16;
+
+#[allow(dead_code)]
+fn encode(tx: &Transaction) -> [u8; RECORD_SIZE] {
+    let mut buf = [0u8; RECORD_SIZE];
+    buf[0] = match tx.tx_type() {
+        TransactionType::deposit => 0,
+        TransactionType::withdrawal => 1,
+        TransactionType::dispute => 2,
+        TransactionType::resolve => 3,
+        TransactionType::chargeback => 4,
+    };
+    buf[1..3].copy_from_slice(&tx.client().to_le_bytes());
+    buf[3..7].copy_from_slice(&tx.tx().to_le_bytes());
+    match tx.amount() {
+        Some(a) => {
+            buf[7] = 1;
+            buf[8..16].copy_from_slice(&a.to_raw().to_le_bytes());
+        },
+        None => buf[7] = 0,
+    }
+    buf
+}
+
+#[allow(dead_code)]
+fn decode(buf: &[u8; RECORD_SIZE]) -> Transaction {
+    let tx_type = match buf[0] {
+        0 => TransactionType::deposit,
+        1 => TransactionType::withdrawal,
+        2 => TransactionType::dispute,
+        3 => TransactionType::resolve,
+        _ => TransactionType::chargeback,
+    };
+    let client = u16::from_le_bytes([buf[1], buf[2]]);
+    let tx = u32::from_le_bytes([buf[3], buf[4], buf[5], buf[6]]);
+    let amount = if buf[7] == 1 {
+        Some(Decimal::from_raw(i64::from_le_bytes(buf[8..16].try_into().unwrap())))
+    } else {
+        None
+    };
+    Transaction::from(tx_type, client, tx, amount)
+}
+
+//Spills transactions to a backing file instead of holding them all in
+//memory, so a multi-gigabyte input doesn't exhaust RAM. Only a byte-offset
+//index is kept in memory; `get` seeks and reads the record back from disk.
+#[allow(dead_code)]
+pub struct DiskTransactionStore {
+    file: RefCell<File>,
+    path: std::path::PathBuf,
+    index: HashMap<u32, u64>,
+    next_offset: u64,
+}
+
+//Process-wide counter so each `DiskTransactionStore` gets its own backing
+//file even when several are created concurrently in the same process.
+#[allow(dead_code)]
+static NEXT_STORE_ID: AtomicU64 = AtomicU64::new(0);
+
+impl Default for DiskTransactionStore {
+    fn default() -> Self {
+        let store_id = NEXT_STORE_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "payments_engine_txstore_{}_{}.bin",
+            std::process::id(),
+            store_id
+        ));
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .expect("failed to create transaction store backing file");
+        DiskTransactionStore {
+            file: RefCell::new(file),
+            path,
+            index: HashMap::new(),
+            next_offset: 0,
+        }
+    }
+}
+
+//Out-of-core processing shouldn't trade unbounded RAM growth for unbounded
+//disk growth, so the backing file is removed once the store is dropped.
+impl Drop for DiskTransactionStore {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl TransactionStore for DiskTransactionStore {
+    fn insert(&mut self, tx: &Transaction) {
+        let buf = encode(tx);
+        let offset = self.next_offset;
+        let mut file = self.file.borrow_mut();
+        file.seek(SeekFrom::Start(offset)).expect("seek failed");
+        file.write_all(&buf).expect("write failed");
+        self.index.insert(*tx.tx(), offset);
+        self.next_offset += RECORD_SIZE as u64;
+    }
+
+    fn get(&self, id: &u32) -> Option<Transaction> {
+        let offset = *self.index.get(id)?;
+        let mut file = self.file.borrow_mut();
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut buf = [0u8; RECORD_SIZE];
+        file.read_exact(&mut buf).ok()?;
+        Some(decode(&buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_store_roundtrip() {
+        let mut store = InMemoryTransactionStore::default();
+        let tx = Transaction::from(TransactionType::deposit, 1, 1, Some("10.0".parse().unwrap()));
+        store.insert(&tx);
+        assert_eq!(store.get(&1), Some(tx));
+        assert_eq!(store.get(&2), None);
+    }
+
+    #[test]
+    fn test_disk_store_roundtrip() {
+        let mut store = DiskTransactionStore::default();
+        let deposit = Transaction::from(TransactionType::deposit, 1, 1, Some("10.0".parse().unwrap()));
+        let dispute = Transaction::from(TransactionType::dispute, 1, 2, None);
+        store.insert(&deposit);
+        store.insert(&dispute);
+
+        assert_eq!(store.get(&1), Some(deposit));
+        assert_eq!(store.get(&2), Some(dispute));
+        assert_eq!(store.get(&3), None);
+    }
+}
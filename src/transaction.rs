@@ -1,4 +1,5 @@
 use std::{cmp::Ordering};
+use crate::decimal::Decimal;
 
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -15,7 +16,7 @@ pub struct Transaction {
     #[serde(rename = "type")]
     tx_type: TransactionType,
     client: u16,
-    amount: Option<f64>,
+    amount: Option<Decimal>,
     tx: u32,
 }
 
@@ -24,11 +25,11 @@ impl Transaction {
     pub fn from(tx_type: TransactionType,
                 client: u16,
                 tx: u32,
-                amount: Option<f64>) -> Self {
+                amount: Option<Decimal>) -> Self {
                     Self{tx_type, client, amount, tx}
                 }
 
-    pub fn amount(&self) -> Option<f64>{
+    pub fn amount(&self) -> Option<Decimal>{
         self.amount
     }
 
@@ -80,8 +81,8 @@ mod tests {
     fn test_csv() {
         let mut rdr = csv::Reader::from_reader(INPUT.as_bytes());
         let mut expected = BTreeSet::<Transaction>::new();
-        expected.insert(Transaction{tx_type:TransactionType::deposit,client: 1, tx: 1, amount: Some(0.0)});
-        expected.insert(Transaction{tx_type:TransactionType::withdrawal,client: 2, tx: 2, amount: Some(2.0)});
+        expected.insert(Transaction{tx_type:TransactionType::deposit,client: 1, tx: 1, amount: Some("1.0".parse().unwrap())});
+        expected.insert(Transaction{tx_type:TransactionType::withdrawal,client: 2, tx: 2, amount: Some("2.0".parse().unwrap())});
         expected.insert(Transaction{tx_type:TransactionType::dispute,client: 1, tx: 2, amount: None});
         expected.insert(Transaction{tx_type:TransactionType::resolve,client: 1, tx: 2, amount: None});
         expected.insert(Transaction{tx_type:TransactionType::chargeback,client: 1, tx: 2, amount: None});